@@ -34,19 +34,38 @@ use clap::{
 	Parser,
 	ArgAction
 };
+use flate2::{
+	Compression,
+	write::{
+		GzEncoder,
+		DeflateEncoder
+	}
+};
 
 const CAPACITY: usize = 128;
 const REQUEST_SIZE: usize = 1024;
+const COMPRESSION_THRESHOLD: usize = 256;
 const SERVER_TOKEN: Token = Token(0);
 
 struct Session {
 	client: TcpStream,
-	response: Option<Vec<u8>>
+	read_buffer: Vec<u8>,
+	response: Option<Vec<u8>>,
+	written: usize,
+	peer_requested_close: bool
 }
 
 struct Cache {
 	content: Arc<Vec<u8>>,
-	modified_timestamp: u64
+	modified_timestamp: u64,
+	compressed: HashMap<String, Arc<Vec<u8>>>
+}
+
+struct ServerContext<'a> {
+	base_dir: &'a str,
+	caches: &'a mut HashMap<PathBuf, Cache>,
+	mime_types: &'a Arc<HashMap<String, String>>,
+	allow_upload: bool
 }
 
 #[derive(Parser)]
@@ -62,13 +81,19 @@ struct Args {
 	port: u16,
 
 	#[arg(short, default_value = "static", help = "Serve the directory files")]
-	base_dir: String
+	base_dir: String,
+
+	#[arg(short, default_value = "/etc/mime.types", help = "Load the extension to MIME type table from this file")]
+	mime_types: String,
+
+	#[arg(short, help = "Allow file uploads via PUT and multipart POST")]
+	allow_upload: bool
 }
 
 fn main() {
 	let args = Args::parse();
 
-	if let Err(error) = run_server(args.port, args.base_dir) {
+	if let Err(error) = run_server(args.port, args.base_dir, args.mime_types, args.allow_upload) {
 		if permission_denied(&error) {
 			eprintln!("Permission denied, you need administrator privileges.");
 
@@ -79,7 +104,7 @@ fn main() {
 	}
 }
 
-fn run_server(port: u16, base_dir: String) -> Result<(), Error> {
+fn run_server(port: u16, base_dir: String, mime_types_path: String, allow_upload: bool) -> Result<(), Error> {
 	let mut poll = Poll::new()?;
 
 	let mut events = Events::with_capacity(CAPACITY);
@@ -97,6 +122,8 @@ fn run_server(port: u16, base_dir: String) -> Result<(), Error> {
 
 	let mut caches: HashMap<PathBuf, Cache> = HashMap::new();
 
+	let mime_types = Arc::new(load_mime_types(&mime_types_path));
+
 	println!("Running on: 0.0.0.0:{}", port);
 
 	loop {
@@ -120,7 +147,14 @@ fn run_server(port: u16, base_dir: String) -> Result<(), Error> {
 					}
 				},
 				Token(client_id) => {
-					if let Err(_) = handle_client(&mut sessions, client_id, &event, &poll, base_dir.clone(), &mut caches) {
+					let mut context = ServerContext {
+						base_dir: &base_dir,
+						caches: &mut caches,
+						mime_types: &mime_types,
+						allow_upload
+					};
+
+					if let Err(_) = handle_client(&mut sessions, client_id, &event, &poll, &mut context) {
 						close_client(&mut sessions, client_id, &poll);
 					}
 				}
@@ -129,6 +163,40 @@ fn run_server(port: u16, base_dir: String) -> Result<(), Error> {
 	}
 }
 
+fn load_mime_types(path: &str) -> HashMap<String, String> {
+	let mut mime_types = HashMap::new();
+
+	let content = match fs::read_to_string(path) {
+		Ok(content) => content,
+		Err(_) => {
+			return mime_types;
+		}
+	};
+
+	for line in content.lines() {
+		let line = line.trim();
+
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		let mut tokens = line.split_whitespace();
+
+		let mime_type = match tokens.next() {
+			Some(mime_type) => mime_type,
+			None => {
+				continue;
+			}
+		};
+
+		for extension in tokens {
+			mime_types.insert(extension.to_string(), mime_type.to_string());
+		}
+	}
+
+	mime_types
+}
+
 fn interrupted(error: &Error) -> bool {
 	error.kind() == ErrorKind::Interrupted
 }
@@ -141,7 +209,10 @@ fn accept_client(server: &TcpListener, poll: &Poll, next_client_id: &mut usize,
 
 	sessions.insert(*next_client_id, Session {
 		client: client,
-		response: None
+		read_buffer: Vec::new(),
+		response: None,
+		written: 0,
+		peer_requested_close: false
 	});
 
 	*next_client_id += 1;
@@ -153,20 +224,22 @@ fn would_block(error: &Error) -> bool {
 	error.kind() == ErrorKind::WouldBlock
 }
 
-fn handle_client(sessions: &mut HashMap<usize, Session>, client_id: usize, event: &Event, poll: &Poll, base_dir: String, caches: &mut HashMap<PathBuf, Cache>) -> Result<(), Error> {
+fn handle_client(sessions: &mut HashMap<usize, Session>, client_id: usize, event: &Event, poll: &Poll, context: &mut ServerContext) -> Result<(), Error> {
 	if let Some(session) = sessions.get_mut(&client_id) {
 		if event.is_readable() {
-			let mut data = vec![0; REQUEST_SIZE];
+			let mut chunk = [0; REQUEST_SIZE];
 
-			let mut bytes_readed = 0;
+			let mut got_eof = false;
 
 			loop {
-				match session.client.read(&mut data[bytes_readed..]) {
+				match session.client.read(&mut chunk) {
 					Ok(0) => {
+						got_eof = true;
+
 						break;
 					},
 					Ok(n) => {
-						bytes_readed += n;
+						session.read_buffer.extend_from_slice(&chunk[..n]);
 					},
 					Err(error) => {
 						if interrupted(&error) {
@@ -182,131 +255,418 @@ fn handle_client(sessions: &mut HashMap<usize, Session>, client_id: usize, event
 				};
 			}
 
-			if bytes_readed == 0 {
-				return Err(Error::new(ErrorKind::Other, ""));
+			if session.read_buffer.is_empty() {
+				if got_eof {
+					return Err(Error::new(ErrorKind::Other, ""));
+				}
+
+				return Ok(());
+			}
+
+			let complete = has_complete_request(&session.read_buffer);
+
+			if !got_eof && !complete {
+				return Ok(());
 			}
 
-			data.truncate(bytes_readed);
+			let data = if complete {
+				take_next_request(&mut session.read_buffer)
+			} else {
+				std::mem::take(&mut session.read_buffer)
+			};
 
 			poll.registry()
 				.reregister(&mut session.client, Token(client_id), Interest::WRITABLE)?;
 
-			let response = handle_request(data, base_dir, caches);
+			let (response, keep_alive) = handle_request(data, context.base_dir.to_string(), context.caches, context.mime_types.as_ref(), context.allow_upload);
 
+			session.peer_requested_close = got_eof || !keep_alive;
+			session.written = 0;
 			session.response = Some(response);
 
 			return Ok(());
 		}
 
 		if event.is_writable() {
-			if let Some(response) = &session.response {
-				session.client.write_all(&response)?;
+			if let Some(response) = session.response.take() {
+				loop {
+					match session.client.write(&response[session.written..]) {
+						Ok(0) => {
+							break;
+						},
+						Ok(n) => {
+							session.written += n;
+
+							if session.written >= response.len() {
+								break;
+							}
+						},
+						Err(error) => {
+							if interrupted(&error) {
+								continue;
+							}
+
+							if would_block(&error) {
+								session.response = Some(response);
+
+								return Ok(());
+							}
+
+							return Err(error);
+						}
+					};
+				}
 
 				session.client.flush()?;
 			}
 
-			close_client(sessions, client_id, poll);
+			if session.peer_requested_close {
+				close_client(sessions, client_id, poll);
+
+				return Ok(());
+			}
+
+			if has_complete_request(&session.read_buffer) {
+				let data = take_next_request(&mut session.read_buffer);
+
+				let (response, keep_alive) = handle_request(data, context.base_dir.to_string(), context.caches, context.mime_types.as_ref(), context.allow_upload);
+
+				session.peer_requested_close = !keep_alive;
+				session.written = 0;
+				session.response = Some(response);
+
+				poll.registry()
+					.reregister(&mut session.client, Token(client_id), Interest::WRITABLE)?;
 
-			return Ok(())
+				return Ok(());
+			}
+
+			session.written = 0;
+
+			poll.registry()
+				.reregister(&mut session.client, Token(client_id), Interest::READABLE)?;
+
+			return Ok(());
 		}
 	}
 
 	Ok(())
 }
 
-fn handle_request(data: Vec<u8>, base_dir: String, caches: &mut HashMap<PathBuf, Cache>) -> Vec<u8> {
-	let path = match parse_request(data) {
-		Ok(path) => path,
+fn handle_request(data: Vec<u8>, base_dir: String, caches: &mut HashMap<PathBuf, Cache>, mime_types: &HashMap<String, String>, allow_upload: bool) -> (Vec<u8>, bool) {
+	let request = match parse_request(data) {
+		Ok(request) => request,
 		Err(_) => {
-			return build_response("400 Bad Request", HashMap::from([("Content-Type", "text/html")]), bad_request_html());
+			return (build_response("400 Bad Request", HashMap::from([("Content-Type", "text/html".to_string())]), bad_request_html(), false), false);
 		}
 	};
 
-	let full_path = match prevent_directory_transversal(base_dir, path.clone()) {
+	let keep_alive = should_keep_alive(&request.version, &request.headers);
+
+	let respond = |status: &str, headers: HashMap<&str, String>, body: Vec<u8>| build_response(status, headers, body, keep_alive);
+
+	if request.method == "PUT" || request.method == "POST" {
+		return (handle_upload(&request, &base_dir, caches, allow_upload, keep_alive), keep_alive);
+	}
+
+	let full_path = match prevent_directory_transversal(base_dir, request.path.clone()) {
 		Ok(full_path) => full_path,
 		Err(error) => {
 			if permission_denied(&error) {
-				return build_response("403 Forbidden", HashMap::from([("Content-Type", "text/html")]), forbidden_html());
+				return (respond("403 Forbidden", HashMap::from([("Content-Type", "text/html".to_string())]), forbidden_html()), keep_alive);
 			}
 
 			if not_found(&error) {
-				return build_response("404 Not Found", HashMap::from([("Content-Type", "text/html")]), not_found_html())
+				return (respond("404 Not Found", HashMap::from([("Content-Type", "text/html".to_string())]), not_found_html()), keep_alive)
 			}
 
-			return build_response("500 Internal Server Error", HashMap::from([("Content-Type", "text/html")]), internal_server_error_html())
+			return (respond("500 Internal Server Error", HashMap::from([("Content-Type", "text/html".to_string())]), internal_server_error_html()), keep_alive)
 		}
 	};
 
+	let content_type = get_content_type(&full_path, mime_types);
+
+	let is_file = full_path.is_file();
+
+	let mut validators: HashMap<&str, String> = HashMap::new();
+
+	if is_file {
+		let (modified_timestamp, length) = match get_file_metadata(&full_path) {
+			Ok(metadata) => metadata,
+			Err(error) => {
+				if not_found(&error) {
+					return (respond("404 Not Found", HashMap::from([("Content-Type", "text/html".to_string())]), not_found_html()), keep_alive);
+				}
+
+				return (respond("500 Internal Server Error", HashMap::from([("Content-Type", "text/html".to_string())]), internal_server_error_html()), keep_alive);
+			}
+		};
+
+		let etag = generate_etag(length, modified_timestamp);
+
+		if client_has_current_copy(&request.headers, &etag, modified_timestamp) {
+			return (respond("304 Not Modified", HashMap::from([
+				("ETag", etag),
+				("Last-Modified", format_http_date(modified_timestamp))
+			]), Vec::new()), keep_alive);
+		}
+
+		validators.insert("ETag", etag);
+		validators.insert("Last-Modified", format_http_date(modified_timestamp));
+	}
+
 	match get_content_from_cache(caches, full_path.clone()) {
 		Ok(body) => {
 			if let Some(body) = body {
-				return build_response("200 OK", HashMap::new(), body);
+				let mut headers = HashMap::from([("Content-Type", content_type.clone())]);
+
+				headers.extend(validators);
+
+				let options = ResponseOptions {
+					range_header: request.headers.get("range"),
+					accept_encoding: request.headers.get("accept-encoding"),
+					is_file,
+					keep_alive
+				};
+
+				return (build_file_response(caches, &full_path, body, &content_type, headers, &options), keep_alive);
 			}
 		},
 		Err(error) => {
 			if not_found(&error) {
-				return build_response("404 Not Found", HashMap::from([("Content-Type", "text/html")]), not_found_html())
+				return (respond("404 Not Found", HashMap::from([("Content-Type", "text/html".to_string())]), not_found_html()), keep_alive)
 			}
 
-			return build_response("500 Internal Server Error", HashMap::from([("Content-Type", "text/html")]), internal_server_error_html())
+			return (respond("500 Internal Server Error", HashMap::from([("Content-Type", "text/html".to_string())]), internal_server_error_html()), keep_alive)
 		}
 	};
 
-	let body = match get_content(full_path.clone(), path) {
+	let body = match get_content(full_path.clone(), request.path) {
 		Ok(body) => Arc::new(body),
 		Err(error) => {
 			if not_found(&error) {
-				return build_response("404 Not Found", HashMap::from([("Content-Type", "text/html")]), not_found_html());
+				return (respond("404 Not Found", HashMap::from([("Content-Type", "text/html".to_string())]), not_found_html()), keep_alive);
 			}
 
-			return build_response("500 Internal Server Error", HashMap::from([("Content-Type", "text/html")]), internal_server_error_html());
+			return (respond("500 Internal Server Error", HashMap::from([("Content-Type", "text/html".to_string())]), internal_server_error_html()), keep_alive);
 		}
 	};
 
 	match get_modified_timestamp(full_path.clone()) {
 		Ok(current_timestamp) => {
-			caches.insert(full_path, Cache {
+			caches.insert(full_path.clone(), Cache {
 				content: Arc::clone(&body),
-				modified_timestamp: current_timestamp
+				modified_timestamp: current_timestamp,
+				compressed: HashMap::new()
 			});
 		},
 		Err(error) => {
 			if not_found(&error) {
-				return build_response("404 Not Found", HashMap::from([("Content-Type", "text/html")]), not_found_html());
+				return (respond("404 Not Found", HashMap::from([("Content-Type", "text/html".to_string())]), not_found_html()), keep_alive);
 			}
 
-			return build_response("500 Internal Server Error", HashMap::from([("Content-Type", "text/html")]), internal_server_error_html())
+			return (respond("500 Internal Server Error", HashMap::from([("Content-Type", "text/html".to_string())]), internal_server_error_html()), keep_alive)
 		}
 	};
 
-	build_response("200 OK", HashMap::new(), body.to_vec())
+	let mut headers = HashMap::from([("Content-Type", content_type.clone())]);
+
+	headers.extend(validators);
+
+	let options = ResponseOptions {
+		range_header: request.headers.get("range"),
+		accept_encoding: request.headers.get("accept-encoding"),
+		is_file,
+		keep_alive
+	};
+
+	(build_file_response(caches, &full_path, body.to_vec(), &content_type, headers, &options), keep_alive)
 }
 
-fn parse_request(data: Vec<u8>) -> Result<String, Error> {
-	let request = match String::from_utf8(data) {
-		Ok(request) => request,
+fn client_has_current_copy(headers: &HashMap<String, String>, etag: &str, modified_timestamp: u64) -> bool {
+	if let Some(if_none_match) = headers.get("if-none-match") {
+		return if_none_match == etag;
+	}
+
+	if let Some(if_modified_since) = headers.get("if-modified-since") {
+		if let Some(since_timestamp) = parse_http_date(if_modified_since) {
+			return modified_timestamp <= since_timestamp;
+		}
+	}
+
+	false
+}
+
+fn generate_etag(length: u64, modified_timestamp: u64) -> String {
+	format!("\"{}-{}\"", length, modified_timestamp)
+}
+
+fn get_content_type(full_path: &Path, mime_types: &HashMap<String, String>) -> String {
+	if full_path.is_dir() {
+		return String::from("text/html");
+	}
+
+	full_path.extension()
+		.and_then(|extension| extension.to_str())
+		.map(|extension| extension.to_lowercase())
+		.and_then(|extension| mime_types.get(&extension))
+		.cloned()
+		.unwrap_or_else(|| String::from("application/octet-stream"))
+}
+
+struct ParsedRequest {
+	method: String,
+	path: String,
+	version: String,
+	headers: HashMap<String, String>,
+	body: Vec<u8>
+}
+
+fn find_header_end(buffer: &[u8]) -> Option<usize> {
+	buffer.windows(4)
+		.position(|window| window == b"\r\n\r\n")
+		.map(|position| position + 4)
+}
+
+fn parse_headers(headers_text: &str) -> HashMap<String, String> {
+	let mut headers = HashMap::new();
+
+	for line in headers_text.lines().skip(1) {
+		if line.is_empty() {
+			break;
+		}
+
+		if let Some((name, value)) = line.split_once(':') {
+			headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+		}
+	}
+
+	headers
+}
+
+fn parse_content_length(headers: &HashMap<String, String>) -> usize {
+	headers.get("content-length")
+		.and_then(|content_length| content_length.parse().ok())
+		.unwrap_or(0)
+}
+
+fn has_complete_request(buffer: &[u8]) -> bool {
+	let header_end = match find_header_end(buffer) {
+		Some(header_end) => header_end,
+		None => {
+			return false;
+		}
+	};
+
+	let headers_text = match std::str::from_utf8(&buffer[..header_end]) {
+		Ok(headers_text) => headers_text,
 		Err(_) => {
+			return true;
+		}
+	};
+
+	let content_length = parse_content_length(&parse_headers(headers_text));
+
+	buffer.len() >= header_end + content_length
+}
+
+fn take_next_request(buffer: &mut Vec<u8>) -> Vec<u8> {
+	let header_end = match find_header_end(buffer) {
+		Some(header_end) => header_end,
+		None => {
+			return std::mem::take(buffer);
+		}
+	};
+
+	let headers_text = match std::str::from_utf8(&buffer[..header_end]) {
+		Ok(headers_text) => headers_text,
+		Err(_) => {
+			return std::mem::take(buffer);
+		}
+	};
+
+	let content_length = parse_content_length(&parse_headers(headers_text));
+
+	let request_end = (header_end + content_length).min(buffer.len());
+
+	buffer.drain(..request_end).collect()
+}
+
+fn parse_request(data: Vec<u8>) -> Result<ParsedRequest, Error> {
+	let header_end = match find_header_end(&data) {
+		Some(header_end) => header_end,
+		None => {
 			return Err(Error::new(ErrorKind::InvalidData, ""));
 		}
 	};
 
-	let mut lines = request.lines();
+	let headers_text = match std::str::from_utf8(&data[..header_end]) {
+		Ok(headers_text) => headers_text,
+		Err(_) => {
+			return Err(Error::new(ErrorKind::InvalidData, ""));
+		}
+	};
 
-	let first_line = match lines.nth(0) {
+	let first_line = match headers_text.lines().nth(0) {
 		Some(first_line) => first_line,
 		None => {
 			return Err(Error::new(ErrorKind::InvalidData, ""));
 		}
 	};
 
-	let path = match first_line.split(' ').nth(1) {
+	let mut first_line_tokens = first_line.split(' ');
+
+	let method = match first_line_tokens.next() {
+		Some(method) => method.to_string(),
+		None => {
+			return Err(Error::new(ErrorKind::InvalidData, ""));
+		}
+	};
+
+	let path = match first_line_tokens.next() {
 		Some(path) => path.to_string(),
 		None => {
 			return Err(Error::new(ErrorKind::InvalidData, ""));
 		}
 	};
 
-	Ok(path)
+	let version = first_line_tokens.next()
+		.unwrap_or("HTTP/1.0")
+		.to_string();
+
+	let headers = parse_headers(headers_text);
+
+	let content_length = parse_content_length(&headers);
+
+	let body = data[header_end..]
+		.iter()
+		.take(content_length)
+		.cloned()
+		.collect();
+
+	Ok(ParsedRequest {
+		method,
+		path,
+		version,
+		headers,
+		body
+	})
+}
+
+fn should_keep_alive(version: &str, headers: &HashMap<String, String>) -> bool {
+	if let Some(connection) = headers.get("connection") {
+		let connection = connection.to_lowercase();
+
+		if connection == "close" {
+			return false;
+		}
+
+		if connection == "keep-alive" {
+			return true;
+		}
+	}
+
+	version == "HTTP/1.1"
 }
 
 fn bad_request_html() -> Vec<u8> {
@@ -325,14 +685,18 @@ fn internal_server_error_html() -> Vec<u8> {
 	html_boilerplate("Internal Server Error", "<h1>Internal Server Error</h1>")
 }
 
+fn method_not_allowed_html() -> Vec<u8> {
+	html_boilerplate("Method Not Allowed", "<h1>Method Not Allowed</h1>")
+}
+
 fn html_boilerplate(title: &str, content: &str) -> Vec<u8> {
 	format!("<!DOCTYPE html>\n<html lang=\"en\">\n\t<head>\n\t\t<title>{}</title>\n\n\t\t<meta charset=\"UTF-8\"/>\n\t\t<meta name=\"robots\" content=\"noindex\"/>\n\t\t<meta http-equiv=\"X-UA-Compatible\" content=\"IE=edge\"/>\n\t\t<meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n\t</head>\n\t<body>\n\t\t<main>\n\t\t\t{}\n\t\t</main>\n\t</body>\n</html>", title, content).as_bytes().to_vec()
 }
 
-fn build_response(status: &str, headers: HashMap<&str, &str>, mut body: Vec<u8>) -> Vec<u8> {
+fn build_response(status: &str, headers: HashMap<&str, String>, mut body: Vec<u8>, keep_alive: bool) -> Vec<u8> {
 	let mut response = Vec::new();
 
-	response.append(&mut "HTTP/1.0 ".as_bytes().to_vec());
+	response.append(&mut "HTTP/1.1 ".as_bytes().to_vec());
 
 	response.append(&mut status.as_bytes().to_vec());
 
@@ -342,6 +706,12 @@ fn build_response(status: &str, headers: HashMap<&str, &str>, mut body: Vec<u8>)
 		response.append(&mut format!("{}: {}\r\n", header.0, header.1).as_bytes().to_vec());
 	}
 
+	response.append(&mut format!("Content-Length: {}\r\n", body.len()).as_bytes().to_vec());
+
+	let connection = if keep_alive { "keep-alive" } else { "close" };
+
+	response.append(&mut format!("Connection: {}\r\n", connection).as_bytes().to_vec());
+
 	response.append(&mut "Server: Jasmin\r\n".as_bytes().to_vec());
 
 	response.append(&mut "\r\n".as_bytes().to_vec());
@@ -351,6 +721,209 @@ fn build_response(status: &str, headers: HashMap<&str, &str>, mut body: Vec<u8>)
 	response
 }
 
+#[derive(Debug, PartialEq)]
+enum RangeOutcome {
+	Full,
+	Partial(u64, u64),
+	Unsatisfiable
+}
+
+fn resolve_range(range_header: Option<&String>, total: u64) -> RangeOutcome {
+	let range_header = match range_header {
+		Some(range_header) => range_header,
+		None => {
+			return RangeOutcome::Full;
+		}
+	};
+
+	let spec = match range_header.strip_prefix("bytes=") {
+		Some(spec) => spec,
+		None => {
+			return RangeOutcome::Full;
+		}
+	};
+
+	let (start, end) = match spec.split_once('-') {
+		Some(parts) => parts,
+		None => {
+			return RangeOutcome::Full;
+		}
+	};
+
+	if total == 0 {
+		return RangeOutcome::Unsatisfiable;
+	}
+
+	if start.is_empty() {
+		let suffix_length: u64 = match end.parse() {
+			Ok(suffix_length) => suffix_length,
+			Err(_) => {
+				return RangeOutcome::Full;
+			}
+		};
+
+		if suffix_length == 0 {
+			return RangeOutcome::Unsatisfiable;
+		}
+
+		return RangeOutcome::Partial(total.saturating_sub(suffix_length), total - 1);
+	}
+
+	let start: u64 = match start.parse() {
+		Ok(start) => start,
+		Err(_) => {
+			return RangeOutcome::Full;
+		}
+	};
+
+	if start >= total {
+		return RangeOutcome::Unsatisfiable;
+	}
+
+	let end = if end.is_empty() {
+		total - 1
+	} else {
+		match end.parse::<u64>() {
+			Ok(end) => end.min(total - 1),
+			Err(_) => {
+				return RangeOutcome::Full;
+			}
+		}
+	};
+
+	if end < start {
+		return RangeOutcome::Unsatisfiable;
+	}
+
+	RangeOutcome::Partial(start, end)
+}
+
+struct ResponseOptions<'a> {
+	range_header: Option<&'a String>,
+	accept_encoding: Option<&'a String>,
+	is_file: bool,
+	keep_alive: bool
+}
+
+fn build_file_response(caches: &mut HashMap<PathBuf, Cache>, full_path: &PathBuf, body: Vec<u8>, content_type: &str, mut headers: HashMap<&str, String>, options: &ResponseOptions) -> Vec<u8> {
+	if !options.is_file {
+		return build_response("200 OK", headers, body, options.keep_alive);
+	}
+
+	let total = body.len() as u64;
+
+	headers.insert("Accept-Ranges", "bytes".to_string());
+
+	let range_outcome = resolve_range(options.range_header, total);
+
+	if matches!(range_outcome, RangeOutcome::Full) && is_compressible(content_type) && body.len() >= COMPRESSION_THRESHOLD {
+		headers.insert("Vary", "Accept-Encoding".to_string());
+
+		if let Some(coding) = negotiate_encoding(options.accept_encoding) {
+			if let Some(compressed) = get_compressed_body(caches, full_path, coding) {
+				headers.insert("Content-Encoding", coding.to_string());
+
+				return build_response("200 OK", headers, compressed, options.keep_alive);
+			}
+		}
+	}
+
+	match range_outcome {
+		RangeOutcome::Full => build_response("200 OK", headers, body, options.keep_alive),
+		RangeOutcome::Partial(start, end) => {
+			headers.insert("Content-Range", format!("bytes {}-{}/{}", start, end, total));
+
+			let slice = body[start as usize..=end as usize].to_vec();
+
+			build_response("206 Partial Content", headers, slice, options.keep_alive)
+		},
+		RangeOutcome::Unsatisfiable => {
+			headers.insert("Content-Range", format!("bytes */{}", total));
+
+			build_response("416 Range Not Satisfiable", headers, Vec::new(), options.keep_alive)
+		}
+	}
+}
+
+fn get_compressed_body(caches: &mut HashMap<PathBuf, Cache>, full_path: &PathBuf, coding: &str) -> Option<Vec<u8>> {
+	let cache = caches.get(full_path)?;
+
+	if let Some(compressed) = cache.compressed.get(coding) {
+		return Some(compressed.to_vec());
+	}
+
+	let identity = Arc::clone(&cache.content);
+
+	let compressed = compress_body(&identity, coding);
+
+	let cache = caches.get_mut(full_path)?;
+
+	cache.compressed.insert(coding.to_string(), Arc::new(compressed.clone()));
+
+	Some(compressed)
+}
+
+fn compress_body(body: &[u8], coding: &str) -> Vec<u8> {
+	match coding {
+		"gzip" => {
+			let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+
+			if encoder.write_all(body).is_err() {
+				return body.to_vec();
+			}
+
+			encoder.finish().unwrap_or_else(|_| body.to_vec())
+		},
+		"deflate" => {
+			let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+
+			if encoder.write_all(body).is_err() {
+				return body.to_vec();
+			}
+
+			encoder.finish().unwrap_or_else(|_| body.to_vec())
+		},
+		_ => body.to_vec()
+	}
+}
+
+const COMPRESSIBLE_TYPE_PREFIXES: [&str; 6] = [
+	"text/",
+	"application/json",
+	"application/javascript",
+	"application/xml",
+	"application/xhtml+xml",
+	"image/svg+xml"
+];
+
+fn is_compressible(content_type: &str) -> bool {
+	COMPRESSIBLE_TYPE_PREFIXES.iter().any(|prefix| content_type.starts_with(prefix))
+}
+
+fn negotiate_encoding(accept_encoding: Option<&String>) -> Option<&'static str> {
+	let accept_encoding = accept_encoding?.to_lowercase();
+
+	["gzip", "deflate"].into_iter()
+		.find(|coding| encoding_quality(&accept_encoding, coding).unwrap_or(0.0) != 0.0)
+}
+
+fn encoding_quality(accept_encoding: &str, coding: &str) -> Option<f64> {
+	accept_encoding.split(',')
+		.find_map(|directive| {
+			let mut parameters = directive.split(';').map(|parameter| parameter.trim());
+
+			if parameters.next() != Some(coding) {
+				return None;
+			}
+
+			let quality = parameters
+				.find_map(|parameter| parameter.strip_prefix("q=").and_then(|quality| quality.parse::<f64>().ok()))
+				.unwrap_or(1.0);
+
+			Some(quality)
+		})
+}
+
 fn prevent_directory_transversal(base_dir: String, mut path: String) -> Result<PathBuf, Error> {
 	let base_path = Path::new(&base_dir)
 		.canonicalize()?;
@@ -362,16 +935,218 @@ fn prevent_directory_transversal(base_dir: String, mut path: String) -> Result<P
 		path = String::from("index.html");
 	}
 
-	let full_path = base_path.join(path)
-		.canonicalize()?;
+	let joined_path = base_path.join(path);
 
-	if !full_path.starts_with(base_path) {
+	let full_path = match joined_path.canonicalize() {
+		Ok(full_path) => full_path,
+		Err(error) => {
+			if error.kind() != ErrorKind::NotFound {
+				return Err(error);
+			}
+
+			let parent = joined_path.parent()
+				.ok_or_else(|| Error::new(ErrorKind::NotFound, ""))?;
+
+			let file_name = joined_path.file_name()
+				.ok_or_else(|| Error::new(ErrorKind::NotFound, ""))?;
+
+			parent.canonicalize()?.join(file_name)
+		}
+	};
+
+	if !full_path.starts_with(&base_path) {
 		return Err(Error::new(ErrorKind::PermissionDenied, ""));
 	}
 
 	Ok(full_path)
 }
 
+fn handle_upload(request: &ParsedRequest, base_dir: &str, caches: &mut HashMap<PathBuf, Cache>, allow_upload: bool, keep_alive: bool) -> Vec<u8> {
+	if !allow_upload {
+		return build_response("405 Method Not Allowed", HashMap::from([("Content-Type", "text/html".to_string())]), method_not_allowed_html(), keep_alive);
+	}
+
+	if request.method == "PUT" {
+		return handle_put(request, base_dir, caches, keep_alive);
+	}
+
+	handle_multipart_upload(request, base_dir, caches, keep_alive)
+}
+
+fn handle_put(request: &ParsedRequest, base_dir: &str, caches: &mut HashMap<PathBuf, Cache>, keep_alive: bool) -> Vec<u8> {
+	let full_path = match prevent_directory_transversal(base_dir.to_string(), request.path.clone()) {
+		Ok(full_path) => full_path,
+		Err(error) => {
+			if permission_denied(&error) {
+				return build_response("403 Forbidden", HashMap::from([("Content-Type", "text/html".to_string())]), forbidden_html(), keep_alive);
+			}
+
+			if not_found(&error) {
+				return build_response("404 Not Found", HashMap::from([("Content-Type", "text/html".to_string())]), not_found_html(), keep_alive);
+			}
+
+			return build_response("500 Internal Server Error", HashMap::from([("Content-Type", "text/html".to_string())]), internal_server_error_html(), keep_alive);
+		}
+	};
+
+	let existed = full_path.is_file();
+
+	if fs::write(&full_path, &request.body).is_err() {
+		return build_response("500 Internal Server Error", HashMap::from([("Content-Type", "text/html".to_string())]), internal_server_error_html(), keep_alive);
+	}
+
+	caches.remove(&full_path);
+
+	if existed {
+		build_response("204 No Content", HashMap::new(), Vec::new(), keep_alive)
+	} else {
+		build_response("201 Created", HashMap::new(), Vec::new(), keep_alive)
+	}
+}
+
+fn handle_multipart_upload(request: &ParsedRequest, base_dir: &str, caches: &mut HashMap<PathBuf, Cache>, keep_alive: bool) -> Vec<u8> {
+	let boundary = match extract_boundary(&request.headers) {
+		Some(boundary) => boundary,
+		None => {
+			return build_response("400 Bad Request", HashMap::from([("Content-Type", "text/html".to_string())]), bad_request_html(), keep_alive);
+		}
+	};
+
+	let parts = split_multipart_body(&request.body, &boundary);
+
+	let mut uploaded_any = false;
+
+	for part in parts {
+		let filename = match part.filename {
+			Some(filename) => filename,
+			None => {
+				continue;
+			}
+		};
+
+		let target_path = format!("{}/{}", request.path.trim_end_matches('/'), filename);
+
+		let full_path = match prevent_directory_transversal(base_dir.to_string(), target_path) {
+			Ok(full_path) => full_path,
+			Err(_) => {
+				continue;
+			}
+		};
+
+		if fs::write(&full_path, &part.content).is_ok() {
+			caches.remove(&full_path);
+
+			uploaded_any = true;
+		}
+	}
+
+	if !uploaded_any {
+		return build_response("400 Bad Request", HashMap::from([("Content-Type", "text/html".to_string())]), bad_request_html(), keep_alive);
+	}
+
+	build_response("201 Created", HashMap::new(), Vec::new(), keep_alive)
+}
+
+fn extract_boundary(headers: &HashMap<String, String>) -> Option<String> {
+	let content_type = headers.get("content-type")?;
+
+	if !content_type.to_lowercase().starts_with("multipart/form-data") {
+		return None;
+	}
+
+	let (_, boundary) = content_type.split_once("boundary=")?;
+
+	let boundary = boundary.trim()
+		.split(';')
+		.next()?
+		.trim()
+		.trim_matches('"');
+
+	if boundary.is_empty() {
+		return None;
+	}
+
+	Some(boundary.to_string())
+}
+
+struct MultipartPart {
+	filename: Option<String>,
+	content: Vec<u8>
+}
+
+fn split_multipart_body(body: &[u8], boundary: &str) -> Vec<MultipartPart> {
+	let delimiter = format!("--{}", boundary).into_bytes();
+
+	let mut sections = Vec::new();
+
+	let mut start = 0;
+
+	while let Some(offset) = find_subslice(&body[start..], &delimiter) {
+		let section = &body[start..start + offset];
+
+		if !section.is_empty() {
+			sections.push(section);
+		}
+
+		start += offset + delimiter.len();
+	}
+
+	sections.into_iter()
+		.filter_map(parse_multipart_section)
+		.collect()
+}
+
+fn parse_multipart_section(section: &[u8]) -> Option<MultipartPart> {
+	let section = trim_multipart_padding(section);
+
+	if section.is_empty() {
+		return None;
+	}
+
+	let header_end = find_header_end(section)?;
+
+	let headers_text = std::str::from_utf8(&section[..header_end]).ok()?;
+
+	let filename = headers_text.lines()
+		.find(|line| line.to_lowercase().starts_with("content-disposition"))
+		.and_then(extract_filename);
+
+	let content = trim_multipart_padding(&section[header_end..]).to_vec();
+
+	Some(MultipartPart { filename, content })
+}
+
+fn trim_multipart_padding(data: &[u8]) -> &[u8] {
+	let data = data.strip_prefix(b"\r\n").unwrap_or(data);
+
+	data.strip_suffix(b"--\r\n")
+		.or_else(|| data.strip_suffix(b"\r\n"))
+		.unwrap_or(data)
+}
+
+fn extract_filename(header_line: &str) -> Option<String> {
+	let (_, rest) = header_line.split_once("filename=")?;
+
+	let filename = rest.split(';')
+		.next()?
+		.trim()
+		.trim_matches('"');
+
+	if filename.is_empty() {
+		return None;
+	}
+
+	Some(filename.to_string())
+}
+
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+	if needle.is_empty() || haystack.len() < needle.len() {
+		return None;
+	}
+
+	haystack.windows(needle.len()).position(|window| window == needle)
+}
+
 fn permission_denied(error: &Error) -> bool {
 	error.kind() == ErrorKind::PermissionDenied
 }
@@ -407,16 +1182,105 @@ fn get_content_from_cache(caches: &mut HashMap<PathBuf, Cache>, full_path: PathB
 }
 
 fn get_modified_timestamp(full_path: PathBuf) -> Result<u64, Error> {
+	let (modified_timestamp, _) = get_file_metadata(&full_path)?;
+
+	Ok(modified_timestamp)
+}
+
+fn get_file_metadata(full_path: &Path) -> Result<(u64, u64), Error> {
 	let metadata = fs::metadata(full_path)?;
 
-	let timestamp = match metadata.modified()?.duration_since(time::UNIX_EPOCH) {
+	let modified_timestamp = match metadata.modified()?.duration_since(time::UNIX_EPOCH) {
 		Ok(timestamp) => timestamp.as_secs(),
 		Err(_) => {
 			return Err(Error::new(ErrorKind::Other, ""));
 		}
 	};
 
-	Ok(timestamp)
+	Ok((modified_timestamp, metadata.len()))
+}
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+fn format_http_date(timestamp: u64) -> String {
+	let days_since_epoch = (timestamp / 86400) as i64;
+	let seconds_of_day = timestamp % 86400;
+
+	let hours = seconds_of_day / 3600;
+	let minutes = (seconds_of_day % 3600) / 60;
+	let seconds = seconds_of_day % 60;
+
+	let weekday = WEEKDAYS[((days_since_epoch + 4).rem_euclid(7)) as usize];
+
+	let (year, month, day) = civil_from_days(days_since_epoch);
+
+	format!("{}, {:02} {} {} {:02}:{:02}:{:02} GMT", weekday, day, MONTHS[(month - 1) as usize], year, hours, minutes, seconds)
+}
+
+fn parse_http_date(date: &str) -> Option<u64> {
+	let (_, rest) = date.trim().split_once(',')?;
+
+	let mut tokens = rest.trim().split(' ');
+
+	let day: u32 = tokens.next()?.parse().ok()?;
+	let month_name = tokens.next()?;
+	let month = MONTHS.iter().position(|month| *month == month_name)? as u32 + 1;
+	let year: i64 = tokens.next()?.parse().ok()?;
+
+	let mut time_tokens = tokens.next()?.split(':');
+
+	let hours: u64 = time_tokens.next()?.parse().ok()?;
+	let minutes: u64 = time_tokens.next()?.parse().ok()?;
+	let seconds: u64 = time_tokens.next()?.parse().ok()?;
+
+	let days = days_from_civil(year, month, day);
+
+	if days < 0 {
+		return None;
+	}
+
+	Some(days as u64 * 86400 + hours * 3600 + minutes * 60 + seconds)
+}
+
+fn civil_from_days(days_since_epoch: i64) -> (i64, u32, u32) {
+	let z = days_since_epoch + 719468;
+
+	let era = if z >= 0 { z } else { z - 146096 } / 146097;
+
+	let day_of_era = (z - era * 146097) as u64;
+
+	let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+
+	let year = year_of_era as i64 + era * 400;
+
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+
+	let month_position = (5 * day_of_year + 2) / 153;
+
+	let day = (day_of_year - (153 * month_position + 2) / 5 + 1) as u32;
+
+	let month = if month_position < 10 { month_position + 3 } else { month_position - 9 } as u32;
+
+	let year = if month <= 2 { year + 1 } else { year };
+
+	(year, month, day)
+}
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+	let year = if month <= 2 { year - 1 } else { year };
+
+	let era = if year >= 0 { year } else { year - 399 } / 400;
+
+	let year_of_era = (year - era * 400) as u64;
+
+	let month_position = if month > 2 { month - 3 } else { month + 9 } as u64;
+
+	let day_of_year = (153 * month_position + 2) / 5 + day as u64 - 1;
+
+	let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+	era * 146097 + day_of_era as i64 - 719468
 }
 
 fn get_content(full_path: PathBuf, path: String) -> Result<Vec<u8>, Error> {
@@ -462,4 +1326,295 @@ fn close_client(sessions: &mut HashMap<usize, Session>, client_id: usize, poll:
 		.deregister(&mut session.client);
 
 	sessions.remove(&client_id);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn formats_unix_epoch() {
+		assert_eq!(format_http_date(0), "Thu, 01 Jan 1970 00:00:00 GMT");
+	}
+
+	#[test]
+	fn formats_a_known_date() {
+		assert_eq!(format_http_date(784_111_777), "Sun, 06 Nov 1994 08:49:37 GMT");
+	}
+
+	#[test]
+	fn parses_a_known_date() {
+		assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Some(784_111_777));
+	}
+
+	#[test]
+	fn parses_invalid_dates_as_none() {
+		assert_eq!(parse_http_date("not a date"), None);
+		assert_eq!(parse_http_date("Sun, 06 Nov 1994"), None);
+	}
+
+	#[test]
+	fn round_trips_through_format_and_parse() {
+		for timestamp in [0, 1, 86_399, 86_400, 784_111_777, 4_102_444_800] {
+			assert_eq!(parse_http_date(&format_http_date(timestamp)), Some(timestamp));
+		}
+	}
+
+	#[test]
+	fn resolves_full_range_when_no_header_is_sent() {
+		assert_eq!(resolve_range(None, 1000), RangeOutcome::Full);
+	}
+
+	#[test]
+	fn resolves_a_simple_range() {
+		let header = "bytes=0-499".to_string();
+
+		assert_eq!(resolve_range(Some(&header), 1000), RangeOutcome::Partial(0, 499));
+	}
+
+	#[test]
+	fn resolves_an_open_ended_range() {
+		let header = "bytes=500-".to_string();
+
+		assert_eq!(resolve_range(Some(&header), 1000), RangeOutcome::Partial(500, 999));
+	}
+
+	#[test]
+	fn resolves_a_suffix_range() {
+		let header = "bytes=-500".to_string();
+
+		assert_eq!(resolve_range(Some(&header), 1000), RangeOutcome::Partial(500, 999));
+	}
+
+	#[test]
+	fn clamps_an_end_past_the_total_length() {
+		let header = "bytes=900-1999".to_string();
+
+		assert_eq!(resolve_range(Some(&header), 1000), RangeOutcome::Partial(900, 999));
+	}
+
+	#[test]
+	fn rejects_a_start_past_the_total_length() {
+		let header = "bytes=1000-1999".to_string();
+
+		assert_eq!(resolve_range(Some(&header), 1000), RangeOutcome::Unsatisfiable);
+	}
+
+	#[test]
+	fn rejects_an_empty_file() {
+		let header = "bytes=0-10".to_string();
+
+		assert_eq!(resolve_range(Some(&header), 0), RangeOutcome::Unsatisfiable);
+	}
+
+	#[test]
+	fn falls_back_to_full_on_a_malformed_header() {
+		let header = "bytes=abc-def".to_string();
+
+		assert_eq!(resolve_range(Some(&header), 1000), RangeOutcome::Full);
+	}
+
+	#[test]
+	fn negotiates_gzip_when_accepted() {
+		let header = "gzip, deflate".to_string();
+
+		assert_eq!(negotiate_encoding(Some(&header)), Some("gzip"));
+	}
+
+	#[test]
+	fn skips_gzip_when_explicitly_rejected() {
+		let header = "gzip;q=0, deflate".to_string();
+
+		assert_eq!(negotiate_encoding(Some(&header)), Some("deflate"));
+	}
+
+	#[test]
+	fn skips_both_when_explicitly_rejected() {
+		let header = "gzip;q=0, deflate;q=0".to_string();
+
+		assert_eq!(negotiate_encoding(Some(&header)), None);
+	}
+
+	#[test]
+	fn accepts_gzip_with_a_nonzero_quality() {
+		let header = "gzip;q=0.5".to_string();
+
+		assert_eq!(negotiate_encoding(Some(&header)), Some("gzip"));
+	}
+
+	#[test]
+	fn treats_decimal_zero_qualities_as_rejected() {
+		let header = "gzip;q=0.000, deflate".to_string();
+
+		assert_eq!(negotiate_encoding(Some(&header)), Some("deflate"));
+	}
+
+	#[test]
+	fn does_not_let_a_rejected_lookalike_coding_suppress_the_real_one() {
+		let header = "gzipx;q=0, gzip".to_string();
+
+		assert_eq!(negotiate_encoding(Some(&header)), Some("gzip"));
+	}
+
+	#[test]
+	fn does_not_accept_a_lookalike_coding_as_a_real_match() {
+		let header = "gzipfoo".to_string();
+
+		assert_eq!(negotiate_encoding(Some(&header)), None);
+	}
+
+	#[test]
+	fn an_empty_buffer_has_no_complete_request() {
+		assert!(!has_complete_request(b""));
+	}
+
+	#[test]
+	fn a_request_without_a_header_terminator_is_incomplete() {
+		assert!(!has_complete_request(b"GET / HTTP/1.1\r\nHost: example.com\r\n"));
+	}
+
+	#[test]
+	fn a_request_with_no_body_is_complete_as_soon_as_headers_end() {
+		assert!(has_complete_request(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n"));
+	}
+
+	#[test]
+	fn a_request_with_a_body_is_incomplete_until_content_length_bytes_arrive() {
+		let buffer = b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhi";
+
+		assert!(!has_complete_request(buffer));
+	}
+
+	#[test]
+	fn a_request_with_a_body_is_complete_once_content_length_bytes_arrive() {
+		let buffer = b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello";
+
+		assert!(has_complete_request(buffer));
+	}
+
+	#[test]
+	fn a_request_is_still_complete_with_a_pipelined_request_trailing_it() {
+		let buffer = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\nGET /second HTTP/1.1\r\n\r\n";
+
+		assert!(has_complete_request(buffer));
+	}
+
+	#[test]
+	fn taking_from_a_buffer_without_a_header_terminator_drains_it_entirely() {
+		let mut buffer = b"GET / HTTP/1.1\r\nHost".to_vec();
+
+		assert_eq!(take_next_request(&mut buffer), b"GET / HTTP/1.1\r\nHost");
+		assert!(buffer.is_empty());
+	}
+
+	#[test]
+	fn taking_a_single_request_leaves_the_buffer_empty() {
+		let mut buffer = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n".to_vec();
+		let expected = buffer.clone();
+
+		assert_eq!(take_next_request(&mut buffer), expected);
+		assert!(buffer.is_empty());
+	}
+
+	#[test]
+	fn taking_a_pipelined_request_leaves_the_next_one_in_the_buffer() {
+		let first = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n".to_vec();
+		let second = b"GET /second HTTP/1.1\r\nHost: example.com\r\n\r\n".to_vec();
+		let mut buffer = [first.clone(), second.clone()].concat();
+
+		assert_eq!(take_next_request(&mut buffer), first);
+		assert_eq!(buffer, second);
+	}
+
+	#[test]
+	fn taking_a_request_with_a_body_includes_only_its_own_content_length_bytes() {
+		let mut buffer = b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhelloGET /second HTTP/1.1\r\n\r\n".to_vec();
+
+		assert_eq!(take_next_request(&mut buffer), b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello");
+		assert_eq!(buffer, b"GET /second HTTP/1.1\r\n\r\n");
+	}
+
+	#[test]
+	fn extracts_a_quoted_filename() {
+		let header = "Content-Disposition: form-data; name=\"file\"; filename=\"test.txt\"";
+
+		assert_eq!(extract_filename(header), Some("test.txt".to_string()));
+	}
+
+	#[test]
+	fn extracts_an_unquoted_filename() {
+		let header = "Content-Disposition: form-data; name=file; filename=test.txt";
+
+		assert_eq!(extract_filename(header), Some("test.txt".to_string()));
+	}
+
+	#[test]
+	fn extracts_a_filename_followed_by_more_parameters() {
+		let header = "Content-Disposition: form-data; filename=\"test.txt\"; name=\"file\"";
+
+		assert_eq!(extract_filename(header), Some("test.txt".to_string()));
+	}
+
+	#[test]
+	fn treats_an_empty_filename_as_none() {
+		let header = "Content-Disposition: form-data; name=\"file\"; filename=\"\"";
+
+		assert_eq!(extract_filename(header), None);
+	}
+
+	#[test]
+	fn treats_a_missing_filename_as_none() {
+		let header = "Content-Disposition: form-data; name=\"field\"";
+
+		assert_eq!(extract_filename(header), None);
+	}
+
+	#[test]
+	fn parses_a_section_without_a_filename() {
+		let section = b"\r\nContent-Disposition: form-data; name=\"field1\"\r\n\r\nvalue1\r\n";
+
+		let part = parse_multipart_section(section).unwrap();
+
+		assert_eq!(part.filename, None);
+		assert_eq!(part.content, b"value1");
+	}
+
+	#[test]
+	fn parses_a_section_with_a_filename() {
+		let section = b"\r\nContent-Disposition: form-data; name=\"file1\"; filename=\"test.txt\"\r\nContent-Type: text/plain\r\n\r\nfile content\r\n";
+
+		let part = parse_multipart_section(section).unwrap();
+
+		assert_eq!(part.filename, Some("test.txt".to_string()));
+		assert_eq!(part.content, b"file content");
+	}
+
+	#[test]
+	fn an_empty_section_parses_to_none() {
+		assert!(parse_multipart_section(b"\r\n").is_none());
+	}
+
+	#[test]
+	fn splits_a_body_into_its_parts_and_stops_at_the_final_boundary() {
+		let body = [
+			b"--boundary\r\n".as_slice(),
+			b"Content-Disposition: form-data; name=\"field1\"\r\n\r\nvalue1\r\n".as_slice(),
+			b"--boundary\r\n".as_slice(),
+			b"Content-Disposition: form-data; name=\"file1\"; filename=\"test.txt\"\r\n\r\nfile content\r\n".as_slice(),
+			b"--boundary--\r\n".as_slice(),
+		].concat();
+
+		let parts = split_multipart_body(&body, "boundary");
+
+		assert_eq!(parts.len(), 2);
+		assert_eq!(parts[0].filename, None);
+		assert_eq!(parts[0].content, b"value1");
+		assert_eq!(parts[1].filename, Some("test.txt".to_string()));
+		assert_eq!(parts[1].content, b"file content");
+	}
+
+	#[test]
+	fn a_body_with_no_boundary_occurrences_yields_no_parts() {
+		assert!(split_multipart_body(b"not multipart at all", "boundary").is_empty());
+	}
 }
\ No newline at end of file